@@ -0,0 +1,58 @@
+//! Shared hardware setup for the on-target test binaries in `tests/`.
+//!
+//! Wires up RP2040 I2C0 on GP0 (SDA) / GP1 (SCL) at 400 kHz, which is where
+//! the test rig's NCP5623 is expected to be connected.
+
+#![no_std]
+
+use rp2040_hal::{
+    gpio::{
+        bank0::{Gpio0, Gpio1},
+        FunctionI2C, Pin, Pins, PullUp,
+    },
+    i2c::I2C,
+    pac, Clock, Sio, Watchdog,
+};
+
+/// RP2040's I2C0 peripheral, configured for the NCP5623 test rig.
+pub type TestI2c = I2C<
+    pac::I2C0,
+    (
+        Pin<Gpio0, FunctionI2C, PullUp>,
+        Pin<Gpio1, FunctionI2C, PullUp>,
+    ),
+>;
+
+/// Brings up the RP2040 clocks and I2C0 peripheral used by every on-target
+/// test binary.
+pub fn init_i2c() -> TestI2c {
+    let mut pac = pac::Peripherals::take().unwrap();
+    let mut watchdog = Watchdog::new(pac.WATCHDOG);
+    let clocks = rp2040_hal::clocks::init_clocks_and_plls(
+        rp2040_hal::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .unwrap();
+
+    let sio = Sio::new(pac.SIO);
+    let pins = Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    I2C::i2c0(
+        pac.I2C0,
+        pins.gpio0.reconfigure(),
+        pins.gpio1.reconfigure(),
+        rp2040_hal::fugit::RateExtU32::kHz(400),
+        &mut pac.RESETS,
+        clocks.peripheral_clock.freq(),
+    )
+}
@@ -0,0 +1,42 @@
+//! Checks that the driver's byte layout is ACKed by a real NCP5623, for the
+//! cases the `embedded-hal-mock` unit tests in `src/lib.rs` only simulate.
+
+#![no_std]
+#![no_main]
+
+use ncp5623_on_target_tests as _;
+use panic_probe as _;
+
+#[defmt_test::tests]
+mod tests {
+    use defmt::assert;
+    use ncp5623::NCP5623;
+    use ncp5623_on_target_tests::{init_i2c, TestI2c};
+
+    #[init]
+    fn init() -> NCP5623<TestI2c> {
+        NCP5623::new_default_address(init_i2c())
+    }
+
+    #[test]
+    fn single_channel_writes_are_acked(ncp: &mut NCP5623<TestI2c>) {
+        assert!(ncp.set_red(0x11).is_ok());
+        assert!(ncp.set_green(0x12).is_ok());
+        assert!(ncp.set_blue(0x13).is_ok());
+    }
+
+    #[test]
+    fn batched_rgb_write_is_acked(ncp: &mut NCP5623<TestI2c>) {
+        assert!(ncp.set_rgb(0x05, 0x0A, 0x0F).is_ok());
+    }
+
+    #[test]
+    fn arbitrary_batch_is_acked(ncp: &mut NCP5623<TestI2c>) {
+        assert!(ncp
+            .send_commands(&[
+                (ncp5623::Command::SetMaxCurrent, 0x1F),
+                (ncp5623::Command::Shutdown, 0),
+            ])
+            .is_ok());
+    }
+}
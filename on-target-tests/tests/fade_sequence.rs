@@ -0,0 +1,25 @@
+//! Checks that a `fade_to` ramp is ACKed end to end on real hardware.
+
+#![no_std]
+#![no_main]
+
+use ncp5623_on_target_tests as _;
+use panic_probe as _;
+
+#[defmt_test::tests]
+mod tests {
+    use defmt::assert;
+    use ncp5623::NCP5623;
+    use ncp5623_on_target_tests::{init_i2c, TestI2c};
+
+    #[init]
+    fn init() -> NCP5623<TestI2c> {
+        NCP5623::new_default_address(init_i2c())
+    }
+
+    #[test]
+    fn ramps_up_then_down(ncp: &mut NCP5623<TestI2c>) {
+        assert!(ncp.fade_to(0x1F, 0x0A).is_ok());
+        assert!(ncp.fade_to(0x00, 0x0A).is_ok());
+    }
+}
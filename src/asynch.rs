@@ -0,0 +1,154 @@
+//! Async counterpart of [`crate::NCP5623`], built on `embedded-hal-async`.
+//!
+//! Mirrors the blocking driver's full API, but every method is an `async fn`
+//! that `.await`s the underlying [`embedded_hal_async::i2c::I2c::write`]
+//! call instead of blocking, so it can be driven from async executors (e.g.
+//! Embassy) whose I2C peripherals only expose the async trait. The command
+//! encoding, validation and error type are shared with the blocking driver.
+
+use embedded_hal_async::i2c::I2c;
+
+use crate::{build_command, validate_value, Command, Error, DEFAULT_ADDRESS, MAX_BATCH_COMMANDS};
+
+/// Async instance of a NCP5623.
+///
+/// See [`crate::NCP5623`] for details on the API; every method here is the
+/// `async` equivalent of its blocking counterpart.
+pub struct NCP5623<I2C> {
+    i2c: I2C,
+    address: u8,
+    /// Last intensity written through [`NCP5623::fade_to`], used to pick a
+    /// ramp direction on the next call.
+    current: u8,
+}
+
+impl<I2C> NCP5623<I2C>
+where
+    I2C: I2c,
+{
+    pub fn new_default_address(i2c: I2C) -> Self {
+        Self::new_unchecked(i2c, DEFAULT_ADDRESS)
+    }
+
+    pub fn new_unchecked(i2c: I2C, address: u8) -> Self {
+        Self {
+            i2c,
+            address,
+            current: 0,
+        }
+    }
+
+    pub fn new(i2c: I2C, address: u8) -> Result<Self, Error<I2C::Error>> {
+        if address > 127 {
+            return Err(Error::InvalidAddress(address));
+        }
+        Ok(Self::new_unchecked(i2c, address))
+    }
+
+    pub async fn shutdown(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.send_command(Command::Shutdown, 0).await
+    }
+
+    pub async fn set_red(&mut self, pwm_value: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(pwm_value)?;
+        self.send_command(Command::SetRed, pwm_value).await
+    }
+
+    pub async fn set_green(&mut self, pwm_value: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(pwm_value)?;
+        self.send_command(Command::SetGreen, pwm_value).await
+    }
+
+    pub async fn set_blue(&mut self, pwm_value: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(pwm_value)?;
+        self.send_command(Command::SetBlue, pwm_value).await
+    }
+
+    /// Sets all three channels in a single I2C transaction. See
+    /// [`crate::NCP5623::set_rgb`] for details.
+    pub async fn set_rgb(
+        &mut self,
+        red_pwm_value: u8,
+        green_pwm_value: u8,
+        blue_pwm_value: u8,
+    ) -> Result<(), Error<I2C::Error>> {
+        validate_value(red_pwm_value)?;
+        validate_value(green_pwm_value)?;
+        validate_value(blue_pwm_value)?;
+        let buf = [
+            build_command(Command::SetRed, red_pwm_value),
+            build_command(Command::SetGreen, green_pwm_value),
+            build_command(Command::SetBlue, blue_pwm_value),
+        ];
+        self.i2c
+            .write(self.address, &buf)
+            .await
+            .map_err(Error::WriteError)
+    }
+
+    /// Builds and sends an arbitrary batch of commands in a single I2C
+    /// transaction. See [`crate::NCP5623::send_commands`] for details.
+    pub async fn send_commands(
+        &mut self,
+        commands: &[(Command, u8)],
+    ) -> Result<(), Error<I2C::Error>> {
+        if commands.len() > MAX_BATCH_COMMANDS {
+            return Err(Error::BatchTooLarge);
+        }
+        for (_, value) in commands {
+            validate_value(*value)?;
+        }
+        let mut buf = [0u8; MAX_BATCH_COMMANDS];
+        for (byte, (cmd, value)) in buf.iter_mut().zip(commands) {
+            *byte = build_command(*cmd, *value);
+        }
+        self.i2c
+            .write(self.address, &buf[..commands.len()])
+            .await
+            .map_err(Error::WriteError)
+    }
+
+    pub async fn set_max_current(&mut self, max_current: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(max_current)?;
+        self.send_command(Command::SetMaxCurrent, max_current).await
+    }
+
+    pub async fn set_upward_target(&mut self, target: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(target)?;
+        self.send_command(Command::UpwardTarget, target).await
+    }
+
+    pub async fn set_downward_target(&mut self, target: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(target)?;
+        self.send_command(Command::DownwardTarget, target).await
+    }
+
+    pub async fn start_dimming(&mut self, period: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(period)?;
+        self.send_command(Command::DimmingStart, period).await
+    }
+
+    /// Ramps the LED current from its last known intensity to `target` using
+    /// the chip's gradual dimming engine. See [`crate::NCP5623::fade_to`]
+    /// for details.
+    pub async fn fade_to(&mut self, target: u8, step_period: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(target)?;
+        validate_value(step_period)?;
+        if target > self.current {
+            self.send_command(Command::UpwardTarget, target).await?;
+        } else {
+            self.send_command(Command::DownwardTarget, target).await?;
+        }
+        self.send_command(Command::DimmingStart, step_period)
+            .await?;
+        self.current = target;
+        Ok(())
+    }
+
+    pub async fn send_command(&mut self, cmd: Command, value: u8) -> Result<(), Error<I2C::Error>> {
+        self.i2c
+            .write(self.address, &[build_command(cmd, value)])
+            .await
+            .map_err(Error::WriteError)
+    }
+}
@@ -5,10 +5,20 @@
 #[cfg(test)]
 extern crate std;
 
+#[cfg(feature = "async")]
+pub mod asynch;
+pub mod split;
+
 use embedded_hal::i2c::{Error as I2cError, I2c};
 
 const DEFAULT_ADDRESS: u8 = 0x38;
 
+/// Largest batch [`NCP5623::send_commands`] can send in one I2C
+/// transaction. Bounded so the outgoing buffer can be built on the stack
+/// without an allocator; pass more than this many commands and
+/// [`Error::BatchTooLarge`] is returned before anything is written.
+pub const MAX_BATCH_COMMANDS: usize = 8;
+
 /// Error type for the NCP5623 driver
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error<E: I2cError> {
@@ -21,6 +31,9 @@ pub enum Error<E: I2cError> {
     /// An invalid address was passed in to the driver
     /// Valid addresses must be 7 bit addresses, i.e. 0-127 inclusive
     InvalidAddress(u8),
+    /// More commands were passed to [`NCP5623::send_commands`] than fit in
+    /// its [`MAX_BATCH_COMMANDS`]-command buffer
+    BatchTooLarge,
 }
 
 impl<E: I2cError> From<E> for Error<E> {
@@ -50,6 +63,29 @@ pub enum Command {
 pub struct NCP5623<I2C> {
     i2c: I2C,
     address: u8,
+    /// Last intensity written through [`NCP5623::fade_to`], used to pick a
+    /// ramp direction on the next call.
+    current: u8,
+}
+
+/// Checks that `value` is a valid 5 bit (0-31) chip input.
+///
+/// Free function (rather than tied to a single `I2C` impl) so both the
+/// blocking driver and the [`asynch`] driver can share it.
+pub(crate) fn validate_value<E: I2cError>(value: u8) -> Result<(), Error<E>> {
+    if value > 0b11111 {
+        Err(Error::InvalidValue)
+    } else {
+        Ok(())
+    }
+}
+
+/// Encodes a [`Command`] and its value into the single byte the chip expects.
+///
+/// Free function (rather than tied to a single `I2C` impl) so both the
+/// blocking driver and the [`asynch`] driver can share it.
+pub(crate) fn build_command(cmd: Command, value: u8) -> u8 {
+    (cmd as u8) | value
 }
 
 impl<I2C> NCP5623<I2C>
@@ -61,7 +97,11 @@ where
     }
 
     pub fn new_unchecked(i2c: I2C, address: u8) -> Self {
-        Self { i2c, address }
+        Self {
+            i2c,
+            address,
+            current: 0,
+        }
     }
 
     pub fn new(i2c: I2C, address: u8) -> Result<Self, Error<I2C::Error>> {
@@ -76,70 +116,117 @@ where
     }
 
     pub fn set_red(&mut self, pwm_value: u8) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&pwm_value)?;
+        validate_value(pwm_value)?;
         self.send_command(Command::SetRed, pwm_value)
     }
 
     pub fn set_green(&mut self, pwm_value: u8) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&pwm_value)?;
+        validate_value(pwm_value)?;
         self.send_command(Command::SetGreen, pwm_value)
     }
 
     pub fn set_blue(&mut self, pwm_value: u8) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&pwm_value)?;
+        validate_value(pwm_value)?;
         self.send_command(Command::SetBlue, pwm_value)
     }
 
+    /// Sets all three channels in a single I2C transaction.
+    ///
+    /// The three command bytes are packed into one buffer and sent with a
+    /// single [`I2c::write`] call instead of one call per channel, cutting
+    /// the bus round-trips (and their start/stop overhead) from three to
+    /// one. Every value is validated before the buffer is built, so an
+    /// invalid value still returns [`Error::InvalidValue`] without touching
+    /// the bus.
     pub fn set_rgb(
         &mut self,
         red_pwm_value: u8,
         green_pwm_value: u8,
         blue_pwm_value: u8,
     ) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&red_pwm_value)?;
-        Self::validate_value(&green_pwm_value)?;
-        Self::validate_value(&blue_pwm_value)?;
-        self.send_command(Command::SetRed, red_pwm_value)?;
-        self.send_command(Command::SetGreen, green_pwm_value)?;
-        self.send_command(Command::SetBlue, blue_pwm_value)
+        validate_value(red_pwm_value)?;
+        validate_value(green_pwm_value)?;
+        validate_value(blue_pwm_value)?;
+        let buf = [
+            build_command(Command::SetRed, red_pwm_value),
+            build_command(Command::SetGreen, green_pwm_value),
+            build_command(Command::SetBlue, blue_pwm_value),
+        ];
+        self.i2c
+            .write(self.address, &buf)
+            .map_err(Error::WriteError)
+    }
+
+    /// Builds and sends an arbitrary batch of commands in a single I2C
+    /// transaction.
+    ///
+    /// `commands` may hold up to [`MAX_BATCH_COMMANDS`] entries; passing
+    /// more returns [`Error::BatchTooLarge`]. Values are otherwise
+    /// validated up front, so an invalid value returns
+    /// [`Error::InvalidValue`] before anything is written to the bus.
+    pub fn send_commands(&mut self, commands: &[(Command, u8)]) -> Result<(), Error<I2C::Error>> {
+        if commands.len() > MAX_BATCH_COMMANDS {
+            return Err(Error::BatchTooLarge);
+        }
+        for (_, value) in commands {
+            validate_value(*value)?;
+        }
+        let mut buf = [0u8; MAX_BATCH_COMMANDS];
+        for (byte, (cmd, value)) in buf.iter_mut().zip(commands) {
+            *byte = build_command(*cmd, *value);
+        }
+        self.i2c
+            .write(self.address, &buf[..commands.len()])
+            .map_err(Error::WriteError)
     }
 
     pub fn set_max_current(&mut self, max_current: u8) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&max_current)?;
+        validate_value(max_current)?;
         self.send_command(Command::SetMaxCurrent, max_current)
     }
 
     pub fn set_upward_target(&mut self, target: u8) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&target)?;
+        validate_value(target)?;
         self.send_command(Command::UpwardTarget, target)
     }
 
     pub fn set_downward_target(&mut self, target: u8) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&target)?;
+        validate_value(target)?;
         self.send_command(Command::DownwardTarget, target)
     }
 
     pub fn start_dimming(&mut self, period: u8) -> Result<(), Error<I2C::Error>> {
-        Self::validate_value(&period)?;
+        validate_value(period)?;
         self.send_command(Command::DimmingStart, period)
     }
 
-    pub fn send_command(&mut self, cmd: Command, value: u8) -> Result<(), Error<I2C::Error>> {
-        self.i2c
-            .write(self.address.into(), &[Self::build_command(cmd, value)])
-            .map_err(Error::WriteError)
-    }
-
-    fn validate_value(value: &u8) -> Result<(), Error<I2C::Error>> {
-        if *value > 0b11111 {
-            Err(Error::InvalidValue)
+    /// Ramps the LED current from its last known intensity to `target` using
+    /// the chip's gradual dimming engine, instead of jumping straight there.
+    ///
+    /// The direction is picked automatically: [`Command::UpwardTarget`] if
+    /// `target` is above the last intensity written through `fade_to`
+    /// (starting at 0), otherwise [`Command::DownwardTarget`]. [`Command::DimmingStart`]
+    /// is then issued with `step_period` as the per-step time base. Both
+    /// `target` and `step_period` are validated to 0-31. The ramp runs
+    /// entirely in hardware, so this call returns as soon as the two
+    /// commands are written and does not busy-wait for it to finish.
+    pub fn fade_to(&mut self, target: u8, step_period: u8) -> Result<(), Error<I2C::Error>> {
+        validate_value(target)?;
+        validate_value(step_period)?;
+        if target > self.current {
+            self.send_command(Command::UpwardTarget, target)?;
         } else {
-            Ok(())
+            self.send_command(Command::DownwardTarget, target)?;
         }
+        self.send_command(Command::DimmingStart, step_period)?;
+        self.current = target;
+        Ok(())
     }
 
-    fn build_command(cmd: Command, value: u8) -> u8 {
-        (cmd as u8) | value
+    pub fn send_command(&mut self, cmd: Command, value: u8) -> Result<(), Error<I2C::Error>> {
+        self.i2c
+            .write(self.address, &[build_command(cmd, value)])
+            .map_err(Error::WriteError)
     }
 }
 
@@ -147,63 +234,29 @@ where
 mod test {
 
     use super::*;
-    use mockall::{mock, predicate::eq};
-
-    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-    pub enum MockError {
-        Mock,
-    }
-
-    impl embedded_hal::i2c::Error for MockError {
-        fn kind(&self) -> embedded_hal::i2c::ErrorKind {
-            embedded_hal::i2c::ErrorKind::Other
-        }
-    }
-
-    mock! {
-        pub I2C {}
-        impl embedded_hal::i2c::ErrorType for I2C {
-            type Error = MockError;
-        }
-        impl embedded_hal::i2c::I2c<embedded_hal::i2c::SevenBitAddress> for I2C {
-            fn transaction<'a>(
-                &mut self,
-                address: embedded_hal::i2c::SevenBitAddress,
-                operations: &mut [embedded_hal::i2c::Operation<'a>]
-            ) -> Result<(), MockError>;
-
-            fn write(&mut self, address: embedded_hal::i2c::SevenBitAddress, write: &[u8]) -> Result<(), MockError>;
-        }
-    }
+    use embedded_hal::i2c::ErrorKind;
+    use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+    use std::vec;
 
     #[test]
     fn test_build_command() {
-        assert_eq!(NCP5623::<MockI2C>::build_command(Command::Shutdown, 0), 0u8);
-        assert_eq!(
-            NCP5623::<MockI2C>::build_command(Command::SetRed, 0b0110),
-            0b01000110
-        );
-        assert_eq!(
-            NCP5623::<MockI2C>::build_command(Command::SetRed, 0b11111),
-            0b01011111
-        );
-        assert_eq!(
-            NCP5623::<MockI2C>::build_command(Command::SetRed, 0),
-            0b01000000
-        );
+        assert_eq!(build_command(Command::Shutdown, 0), 0u8);
+        assert_eq!(build_command(Command::SetRed, 0b0110), 0b01000110);
+        assert_eq!(build_command(Command::SetRed, 0b11111), 0b01011111);
+        assert_eq!(build_command(Command::SetRed, 0), 0b01000000);
     }
 
     #[test]
     fn test_validate_value() {
-        assert_eq!(NCP5623::<MockI2C>::validate_value(&0b0), Ok(()));
-        assert_eq!(NCP5623::<MockI2C>::validate_value(&0b1010), Ok(()));
-        assert_eq!(NCP5623::<MockI2C>::validate_value(&0b11111), Ok(()));
+        assert_eq!(validate_value::<ErrorKind>(0b0), Ok(()));
+        assert_eq!(validate_value::<ErrorKind>(0b1010), Ok(()));
+        assert_eq!(validate_value::<ErrorKind>(0b11111), Ok(()));
         assert_eq!(
-            NCP5623::<MockI2C>::validate_value(&0b100000),
+            validate_value::<ErrorKind>(0b100000),
             Err(Error::InvalidValue)
         );
         assert_eq!(
-            NCP5623::<MockI2C>::validate_value(&u8::MAX),
+            validate_value::<ErrorKind>(u8::MAX),
             Err(Error::InvalidValue)
         );
     }
@@ -211,149 +264,188 @@ mod test {
     #[test]
     fn test_error_from_write_error() {
         assert_eq!(
-            Error::WriteError(MockError::Mock),
-            Error::from(MockError::Mock)
+            Error::WriteError(ErrorKind::Other),
+            Error::from(ErrorKind::Other)
         );
     }
 
     #[test]
     fn test_new_default_address() {
-        let ncp = NCP5623::new_default_address(MockI2C::default());
-        assert_eq!(u8::from(ncp.address), DEFAULT_ADDRESS);
+        let mut ncp = NCP5623::new_default_address(Mock::new(&[]));
+        assert_eq!(ncp.address, DEFAULT_ADDRESS);
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_new_valid_address() {
-        let mut mock = MockI2C::default();
+        let mut mock = Mock::new(&[]);
         let ncp = NCP5623::new(&mut mock, 0x42).unwrap();
-        assert_eq!(u8::from(ncp.address), 0x42);
+        assert_eq!(ncp.address, 0x42);
+        mock.done();
     }
 
     #[test]
     fn test_new_invalid_address() {
-        let mut mock = MockI2C::default();
+        let mut mock = Mock::new(&[]);
         let ncp = NCP5623::new(&mut mock, 0x80);
         assert!(matches!(ncp, Err(Error::InvalidAddress(0x80))));
+        mock.done();
     }
 
     #[test]
     fn test_shutdown() {
-        let mut mock = MockI2C::default();
-        let data = [0b0u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b0])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.shutdown().unwrap();
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_set_red_ok() {
-        let mut mock = MockI2C::default();
-        let data = [0b01000000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b01000000 | 0x11])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.set_red(0x11).unwrap();
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_set_green_ok() {
-        let mut mock = MockI2C::default();
-        let data = [0b01100000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b01100000 | 0x11])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.set_green(0x11).unwrap();
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_set_blue_ok() {
-        let mut mock = MockI2C::default();
-        let data = [0b10000000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b10000000 | 0x11])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.set_blue(0x11).unwrap();
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_rgb() {
-        let mut mock = MockI2C::default();
-        let data_red = [0b01000000 | 0x11u8];
-        let data_green = [0b01100000 | 0x12u8];
-        let data_blue = [0b10000000 | 0x13u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data_red))
-            .return_const(Ok(()));
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data_green))
-            .return_const(Ok(()));
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data_blue))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(
+            DEFAULT_ADDRESS,
+            vec![0b01000000 | 0x11, 0b01100000 | 0x12, 0b10000000 | 0x13],
+        )];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.set_rgb(0x11, 0x12, 0x13).unwrap();
+        ncp.i2c.done();
+    }
+
+    #[test]
+    fn test_rgb_invalid_value_does_not_touch_bus() {
+        let mut ncp = NCP5623::new_default_address(Mock::new(&[]));
+        assert_eq!(ncp.set_rgb(0x11, 0xFF, 0x13), Err(Error::InvalidValue));
+        ncp.i2c.done();
+    }
+
+    #[test]
+    fn test_send_commands() {
+        let expectations = [Transaction::write(
+            DEFAULT_ADDRESS,
+            vec![0b0, 0b00100000 | 0x11],
+        )];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
+        ncp.send_commands(&[(Command::Shutdown, 0), (Command::SetMaxCurrent, 0x11)])
+            .unwrap();
+        ncp.i2c.done();
+    }
+
+    #[test]
+    fn test_send_commands_invalid_value() {
+        let mut ncp = NCP5623::new_default_address(Mock::new(&[]));
+        assert_eq!(
+            ncp.send_commands(&[(Command::SetMaxCurrent, 0xFF)]),
+            Err(Error::InvalidValue)
+        );
+        ncp.i2c.done();
+    }
+
+    #[test]
+    fn test_send_commands_batch_too_large() {
+        let mut ncp = NCP5623::new_default_address(Mock::new(&[]));
+        let commands = [(Command::SetMaxCurrent, 0x11); MAX_BATCH_COMMANDS + 1];
+        assert_eq!(ncp.send_commands(&commands), Err(Error::BatchTooLarge));
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_max_current() {
-        let mut mock = MockI2C::default();
-        let data = [0b00100000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b00100000 | 0x11])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.set_max_current(0x11).unwrap();
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_upward_target() {
-        let mut mock = MockI2C::default();
-        let data = [0b10100000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b10100000 | 0x11])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.set_upward_target(0x11).unwrap();
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_downward_target() {
-        let mut mock = MockI2C::default();
-        let data = [0b11000000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b11000000 | 0x11])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.set_downward_target(0x11).unwrap();
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_start_dimming() {
-        let mut mock = MockI2C::default();
-        let data = [0b11100000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Ok(()));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b11100000 | 0x11])];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         ncp.start_dimming(0x11).unwrap();
+        ncp.i2c.done();
+    }
+
+    #[test]
+    fn test_fade_to_upward() {
+        let expectations = [
+            Transaction::write(DEFAULT_ADDRESS, vec![0b10100000 | 0x11]),
+            Transaction::write(DEFAULT_ADDRESS, vec![0b11100000 | 0x05]),
+        ];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
+        ncp.fade_to(0x11, 0x05).unwrap();
+        assert_eq!(ncp.current, 0x11);
+        ncp.i2c.done();
+    }
+
+    #[test]
+    fn test_fade_to_downward() {
+        let expectations = [
+            Transaction::write(DEFAULT_ADDRESS, vec![0b11000000 | 0x05]),
+            Transaction::write(DEFAULT_ADDRESS, vec![0b11100000 | 0x02]),
+        ];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
+        ncp.current = 0x11;
+        ncp.fade_to(0x05, 0x02).unwrap();
+        assert_eq!(ncp.current, 0x05);
+        ncp.i2c.done();
+    }
+
+    #[test]
+    fn test_fade_to_invalid_value() {
+        let mut ncp = NCP5623::new_default_address(Mock::new(&[]));
+        assert_eq!(ncp.fade_to(0xFF, 0x05), Err(Error::InvalidValue));
+        assert_eq!(ncp.fade_to(0x05, 0xFF), Err(Error::InvalidValue));
+        ncp.i2c.done();
     }
 
     #[test]
     fn test_send_command_err() {
-        let mut mock = MockI2C::default();
-        let data = [0b11000000 | 0x11u8];
-        mock.expect_write()
-            .with(eq(DEFAULT_ADDRESS), eq(data))
-            .return_const(Err(MockError::Mock));
-        let mut ncp = NCP5623::new_default_address(mock);
+        let expectations = [Transaction::write(DEFAULT_ADDRESS, vec![0b11000000 | 0x11])
+            .with_error(ErrorKind::Other)];
+        let mut ncp = NCP5623::new_default_address(Mock::new(&expectations));
         assert_eq!(
             ncp.set_downward_target(0x11),
-            Err(Error::WriteError(MockError::Mock))
+            Err(Error::WriteError(ErrorKind::Other))
         );
+        ncp.i2c.done();
     }
 }
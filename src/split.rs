@@ -0,0 +1,138 @@
+//! Splits a shared [`NCP5623`] into independent per-channel handles.
+//!
+//! Mirrors the `split()` pattern used by drivers like `port-expander` for
+//! PCF8575/MAX7321: the driver lives behind a [`PortMutex`], and each
+//! channel handle only borrows it for the duration of a single call, so a
+//! handle can be handed to a subsystem that should only ever touch its own
+//! channel.
+
+use core::cell::RefCell;
+use core::marker::PhantomData;
+
+use embedded_hal::i2c::I2c;
+
+use crate::{Error, NCP5623};
+
+/// A mutex abstraction that lets a shared [`NCP5623`] be locked briefly by
+/// each [`RedChannel`]/[`GreenChannel`]/[`BlueChannel`] handle.
+///
+/// Implemented for [`RefCell`] for single-core, non-preemptive use out of
+/// the box. Implement it for a critical-section mutex (or your RTOS's
+/// mutex) to share a [`Driver`] across tasks or interrupt contexts.
+pub trait PortMutex {
+    /// The type being protected by the mutex.
+    type Port;
+
+    /// Creates a new mutex wrapping `v`.
+    fn create(v: Self::Port) -> Self;
+
+    /// Locks the mutex and gives a closure access to the wrapped value.
+    fn lock<R>(&self, f: impl FnOnce(&mut Self::Port) -> R) -> R;
+}
+
+impl<T> PortMutex for RefCell<T> {
+    type Port = T;
+
+    fn create(v: Self::Port) -> Self {
+        RefCell::new(v)
+    }
+
+    fn lock<R>(&self, f: impl FnOnce(&mut Self::Port) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+/// A [`NCP5623`] shared behind a [`PortMutex`] and split into per-channel
+/// handles.
+pub struct Driver<M> {
+    mutex: M,
+}
+
+impl<I2C> Driver<RefCell<NCP5623<I2C>>>
+where
+    I2C: I2c,
+{
+    /// Wraps `ncp` in the default [`RefCell`]-based mutex.
+    pub fn new(ncp: NCP5623<I2C>) -> Self {
+        Self::with_mutex(ncp)
+    }
+}
+
+impl<I2C, M> Driver<M>
+where
+    I2C: I2c,
+    M: PortMutex<Port = NCP5623<I2C>>,
+{
+    /// Wraps `ncp` in a caller-chosen [`PortMutex`] implementation.
+    pub fn with_mutex(ncp: NCP5623<I2C>) -> Self {
+        Self {
+            mutex: M::create(ncp),
+        }
+    }
+
+    /// Splits the driver into independent red/green/blue channel handles.
+    pub fn split(
+        &self,
+    ) -> (
+        RedChannel<'_, I2C, M>,
+        GreenChannel<'_, I2C, M>,
+        BlueChannel<'_, I2C, M>,
+    ) {
+        (
+            RedChannel {
+                mutex: &self.mutex,
+                _i2c: PhantomData,
+            },
+            GreenChannel {
+                mutex: &self.mutex,
+                _i2c: PhantomData,
+            },
+            BlueChannel {
+                mutex: &self.mutex,
+                _i2c: PhantomData,
+            },
+        )
+    }
+}
+
+macro_rules! channel {
+    ($name:ident, $doc:literal, $set:ident) => {
+        #[doc = $doc]
+        pub struct $name<'a, I2C, M> {
+            mutex: &'a M,
+            _i2c: PhantomData<I2C>,
+        }
+
+        impl<'a, I2C, M> $name<'a, I2C, M>
+        where
+            I2C: I2c,
+            M: PortMutex<Port = NCP5623<I2C>>,
+        {
+            /// Sets this channel's intensity (0-31).
+            pub fn set(&mut self, value: u8) -> Result<(), Error<I2C::Error>> {
+                self.mutex.lock(|ncp| ncp.$set(value))
+            }
+
+            /// Turns this channel off.
+            pub fn off(&mut self) -> Result<(), Error<I2C::Error>> {
+                self.set(0)
+            }
+        }
+    };
+}
+
+channel!(
+    RedChannel,
+    "A handle to the shared driver's red channel.",
+    set_red
+);
+channel!(
+    GreenChannel,
+    "A handle to the shared driver's green channel.",
+    set_green
+);
+channel!(
+    BlueChannel,
+    "A handle to the shared driver's blue channel.",
+    set_blue
+);